@@ -1,11 +1,61 @@
 #![no_std]
 use soroban_sdk::{
     contract, contractimpl, contracttype, symbol_short,
-    Address, Bytes, BytesN, Env, String, FromVal, IntoVal,
+    xdr::ToXdr,
+    Address, Bytes, BytesN, Env, String, Vec, FromVal, IntoVal,
 };
 
 const DAY_IN_LEDGERS: u32 = 17280; // ~24 hours in Stellar ledgers
 
+/// Derives a swap ID from the parties and nonce rather than the hashlock alone,
+/// so the same (or a re-used) hashlock can back multiple live swaps at once.
+fn derive_swap_id(
+    env: &Env,
+    initiator: &Address,
+    participant: &Address,
+    nonce: u32,
+    hashlock: &BytesN<32>,
+) -> BytesN<32> {
+    let mut data = Bytes::new(env);
+    data.append(&initiator.to_xdr(env));
+    data.append(&participant.to_xdr(env));
+    data.append(&Bytes::from_array(env, &nonce.to_be_bytes()));
+    data.append(&Bytes::from_array(env, &hashlock.to_array()));
+    env.crypto().sha256(&data)
+}
+
+/// Appends `swap_id` to `who`'s swap index so off-chain indexers can page
+/// through a party's full swap history without scanning ledger events.
+/// `extend_to` mirrors the TTL bump on the `Swap` entry itself, so the index
+/// doesn't expire before the swaps it references do.
+fn index_swap(env: &Env, who: &Address, swap_id: &BytesN<32>, extend_to: u32) {
+    let key = DataKey::SwapIndex(who.clone());
+    let mut index: Vec<BytesN<32>> = env
+        .storage()
+        .persistent()
+        .get(&key)
+        .unwrap_or_else(|| Vec::new(env));
+    index.push_back(swap_id.clone());
+    env.storage().persistent().set(&key, &index);
+    env.storage().persistent().extend_ttl(&key, 0, extend_to);
+}
+
+/// Hashes `preimage` with the swap's chosen algorithm so the same secret can
+/// unlock a Stellar-side sha256 lock or an Ethereum-side keccak256 lock.
+fn hash_preimage(env: &Env, algo: &HashAlgo, preimage: &Bytes) -> BytesN<32> {
+    match algo {
+        HashAlgo::Sha256 => env.crypto().sha256(preimage),
+        HashAlgo::Keccak256 => env.crypto().keccak256(preimage),
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+#[contracttype]
+pub enum HashAlgo {
+    Sha256,
+    Keccak256,
+}
+
 #[derive(Clone)]
 #[contracttype]
 pub struct SwapOrder {
@@ -14,9 +64,13 @@ pub struct SwapOrder {
     pub asset: Address,
     pub amount: i128,
     pub hashlock: BytesN<32>,
-    pub timelock: u32,
+    pub hash_algo: HashAlgo,
+    pub cancel_timelock: u32,
+    pub punish_timelock: u32,
     pub withdrawn: bool,
     pub refunded: bool,
+    pub cancelled: bool,
+    pub relayer: Option<Address>,
     pub ethereum_destination: String,
     pub ethereum_amount: String,
     pub ethereum_token: String,
@@ -27,26 +81,41 @@ pub struct SwapOrder {
 pub enum DataKey {
     Swap(BytesN<32>),
     Nonce(Address),
+    SwapIndex(Address),
 }
 
 pub trait StellarHTLCTrait {
     fn initiate_swap(
         env: Env,
+        initiator: Address,
         participant: Address,
         asset: Address,
         amount: i128,
         hashlock: BytesN<32>,
-        timelock: u32,
+        hash_algo: HashAlgo,
+        cancel_timelock: u32,
+        punish_timelock: u32,
+        relayer: Option<Address>,
         ethereum_destination: String,
         ethereum_amount: String,
         ethereum_token: String,
     ) -> BytesN<32>;
-    
+
     fn withdraw(env: Env, swap_id: BytesN<32>, preimage: Bytes);
-    
+
+    fn withdraw_for(env: Env, swap_id: BytesN<32>, preimage: Bytes, to: Address);
+
     fn refund(env: Env, swap_id: BytesN<32>);
-    
+
+    fn cancel(env: Env, swap_id: BytesN<32>, caller: Address);
+
+    fn punish(env: Env, swap_id: BytesN<32>);
+
     fn get_swap(env: Env, swap_id: BytesN<32>) -> SwapOrder;
+
+    fn list_swaps(env: Env, who: Address, start: u32, limit: u32) -> Vec<SwapOrder>;
+
+    fn count_swaps(env: Env, who: Address) -> u32;
 }
 
 #[contract]
@@ -56,29 +125,38 @@ pub struct StellarHTLC;
 impl StellarHTLCTrait for StellarHTLC {
     fn initiate_swap(
         env: Env,
+        initiator: Address,
         participant: Address,
         asset: Address,
         amount: i128,
         hashlock: BytesN<32>,
-        timelock: u32,
+        hash_algo: HashAlgo,
+        cancel_timelock: u32,
+        punish_timelock: u32,
+        relayer: Option<Address>,
         ethereum_destination: String,
         ethereum_amount: String,
         ethereum_token: String,
     ) -> BytesN<32> {
-        let initiator = env.current_contract_address();
-        
+        // Validate caller
+        initiator.require_auth();
+
         // Validate inputs
         assert!(amount > 0, "Amount must be greater than 0");
-        assert!(timelock > env.ledger().sequence() + 120, "Timelock too short"); // ~10 minutes
-        assert!(timelock < env.ledger().sequence() + DAY_IN_LEDGERS, "Timelock too long");
-        
+        assert!(cancel_timelock > env.ledger().sequence() + 120, "Cancel timelock too short"); // ~10 minutes
+        assert!(cancel_timelock < punish_timelock, "Punish timelock must be after cancel timelock");
+        assert!(punish_timelock < env.ledger().sequence() + DAY_IN_LEDGERS, "Punish timelock too long");
+        // Both Sha256 and Keccak256 produce 32-byte digests, so BytesN<32>
+        // already enforces the hashlock length matches the chosen algorithm.
+
         // Get and increment nonce
         let nonce_key = DataKey::Nonce(initiator.clone());
         let nonce: u32 = env.storage().instance().get(&nonce_key).unwrap_or(0);
         env.storage().instance().set(&nonce_key, &(nonce + 1));
-        
-        // Generate swap ID using hashlock directly
-        let swap_id = hashlock.clone();
+
+        // Derive a swap ID unique per (initiator, participant, nonce, hashlock) so
+        // concurrent swaps sharing a hashlock don't collide in `DataKey::Swap`.
+        let swap_id = derive_swap_id(&env, &initiator, &participant, nonce, &hashlock);
         
         // Create swap order
         let swap_order = SwapOrder {
@@ -87,9 +165,13 @@ impl StellarHTLCTrait for StellarHTLC {
             asset: asset.clone(),
             amount,
             hashlock: hashlock.clone(),
-            timelock,
+            hash_algo,
+            cancel_timelock,
+            punish_timelock,
             withdrawn: false,
             refunded: false,
+            cancelled: false,
+            relayer,
             ethereum_destination,
             ethereum_amount,
             ethereum_token,
@@ -98,8 +180,13 @@ impl StellarHTLCTrait for StellarHTLC {
         // Store swap
         let swap_key = DataKey::Swap(swap_id.clone());
         env.storage().persistent().set(&swap_key, &swap_order);
-        env.storage().persistent().extend_ttl(&swap_key, 0, timelock - env.ledger().sequence() + 1000);
-        
+        let ttl_extension = punish_timelock - env.ledger().sequence() + 1000;
+        env.storage().persistent().extend_ttl(&swap_key, 0, ttl_extension);
+
+        // Index the swap under both parties so indexers can page through history
+        index_swap(&env, &initiator, &swap_id, ttl_extension);
+        index_swap(&env, &participant, &swap_id, ttl_extension);
+
         // Transfer tokens to contract
         let token_client = soroban_sdk::token::Client::new(&env, &asset);
         token_client.transfer(&initiator, &env.current_contract_address(), &amount);
@@ -107,7 +194,7 @@ impl StellarHTLCTrait for StellarHTLC {
         // Emit event
         env.events().publish(
             (symbol_short!("swap_init"), swap_id.clone()),
-            (initiator, participant, asset, amount, hashlock, timelock),
+            (initiator, participant, asset, amount, hashlock, cancel_timelock, punish_timelock),
         );
         
         swap_id
@@ -119,69 +206,197 @@ impl StellarHTLCTrait for StellarHTLC {
             .expect("Swap does not exist");
         
         // Validate caller
-        assert_eq!(env.current_contract_address(), swap.participant, "Not swap participant");
-        
+        swap.participant.require_auth();
+
         // Validate state
         assert!(!swap.withdrawn, "Already withdrawn");
         assert!(!swap.refunded, "Already refunded");
-        
+
         // Validate timelock
-        assert!(env.ledger().sequence() < swap.timelock, "Timelock expired");
-        
+        assert!(env.ledger().sequence() < swap.cancel_timelock, "Cancel timelock expired");
+
         // Validate preimage
-        let preimage_hash = env.crypto().sha256(&preimage);
+        let preimage_hash = hash_preimage(&env, &swap.hash_algo, &preimage);
         assert_eq!(preimage_hash, swap.hashlock, "Invalid preimage");
-        
+
         // Update state
         swap.withdrawn = true;
         env.storage().persistent().set(&swap_key, &swap);
-        
+
         // Transfer tokens
         let token_client = soroban_sdk::token::Client::new(&env, &swap.asset);
         token_client.transfer(&env.current_contract_address(), &swap.participant, &swap.amount);
-        
-        // Emit event
+
+        // Emit event - carries the swap id and revealed preimage so the
+        // opposite chain's HTLC can be unlocked with the same secret
         env.events().publish(
             (symbol_short!("withdraw"), swap_id),
             preimage,
         );
     }
-    
+
+    fn withdraw_for(env: Env, swap_id: BytesN<32>, preimage: Bytes, to: Address) {
+        let swap_key = DataKey::Swap(swap_id.clone());
+        let mut swap: SwapOrder = env.storage().persistent().get(&swap_key)
+            .expect("Swap does not exist");
+
+        // Validate caller - only the relayer agreed at setup may complete on
+        // the participant's behalf
+        let relayer = swap.relayer.clone().expect("Swap has no authorized relayer");
+        relayer.require_auth();
+
+        // Validate state
+        assert!(!swap.withdrawn, "Already withdrawn");
+        assert!(!swap.refunded, "Already refunded");
+
+        // Validate timelock
+        assert!(env.ledger().sequence() < swap.cancel_timelock, "Cancel timelock expired");
+
+        // Validate preimage
+        let preimage_hash = hash_preimage(&env, &swap.hash_algo, &preimage);
+        assert_eq!(preimage_hash, swap.hashlock, "Invalid preimage");
+
+        // Update state
+        swap.withdrawn = true;
+        env.storage().persistent().set(&swap_key, &swap);
+
+        // Forward tokens to the relayer-designated recipient
+        let token_client = soroban_sdk::token::Client::new(&env, &swap.asset);
+        token_client.transfer(&env.current_contract_address(), &to, &swap.amount);
+
+        // Emit event - carries the swap id and revealed preimage so the
+        // opposite chain's HTLC can be unlocked with the same secret
+        env.events().publish(
+            (symbol_short!("withdraw"), swap_id),
+            (preimage, to),
+        );
+    }
+
     fn refund(env: Env, swap_id: BytesN<32>) {
         let swap_key = DataKey::Swap(swap_id.clone());
         let mut swap: SwapOrder = env.storage().persistent().get(&swap_key)
             .expect("Swap does not exist");
-        
+
         // Validate caller
-        assert_eq!(env.current_contract_address(), swap.initiator, "Not swap initiator");
-        
+        swap.initiator.require_auth();
+
         // Validate state
         assert!(!swap.withdrawn, "Already withdrawn");
         assert!(!swap.refunded, "Already refunded");
-        
-        // Validate timelock
-        assert!(env.ledger().sequence() >= swap.timelock, "Timelock not expired");
-        
+
+        // Refund is only available once the swap has been cancelled
+        assert!(swap.cancelled, "Swap not cancelled");
+
         // Update state
         swap.refunded = true;
         env.storage().persistent().set(&swap_key, &swap);
-        
+
         // Transfer tokens back
         let token_client = soroban_sdk::token::Client::new(&env, &swap.asset);
         token_client.transfer(&env.current_contract_address(), &swap.initiator, &swap.amount);
-        
+
         // Emit event
         env.events().publish(
             (symbol_short!("refund"), swap_id),
             swap.initiator,
         );
     }
-    
+
+    fn cancel(env: Env, swap_id: BytesN<32>, caller: Address) {
+        let swap_key = DataKey::Swap(swap_id.clone());
+        let mut swap: SwapOrder = env.storage().persistent().get(&swap_key)
+            .expect("Swap does not exist");
+
+        // Validate caller - either party may cancel a stalled swap
+        caller.require_auth();
+        assert!(
+            caller == swap.initiator || caller == swap.participant,
+            "Not a party to this swap"
+        );
+
+        // Validate state
+        assert!(!swap.withdrawn, "Already withdrawn");
+        assert!(!swap.refunded, "Already refunded");
+        assert!(!swap.cancelled, "Already cancelled");
+
+        // Validate timelock
+        assert!(env.ledger().sequence() >= swap.cancel_timelock, "Cancel timelock not reached");
+
+        // Update state - opens the refund window
+        swap.cancelled = true;
+        env.storage().persistent().set(&swap_key, &swap);
+
+        // Emit event
+        env.events().publish(
+            (symbol_short!("cancel"), swap_id),
+            caller,
+        );
+    }
+
+    fn punish(env: Env, swap_id: BytesN<32>) {
+        let swap_key = DataKey::Swap(swap_id.clone());
+        let mut swap: SwapOrder = env.storage().persistent().get(&swap_key)
+            .expect("Swap does not exist");
+
+        // Validate caller
+        swap.initiator.require_auth();
+
+        // Validate state - punish is the fallback when the participant never
+        // withdrew or cancelled; once cancelled, the initiator's recourse is
+        // refund instead
+        assert!(!swap.withdrawn, "Already withdrawn");
+        assert!(!swap.refunded, "Already refunded");
+        assert!(!swap.cancelled, "Swap already cancelled");
+
+        // Validate timelock
+        assert!(env.ledger().sequence() >= swap.punish_timelock, "Punish timelock not reached");
+
+        // Update state
+        swap.refunded = true;
+        env.storage().persistent().set(&swap_key, &swap);
+
+        // Sweep the locked amount back to the initiator
+        let token_client = soroban_sdk::token::Client::new(&env, &swap.asset);
+        token_client.transfer(&env.current_contract_address(), &swap.initiator, &swap.amount);
+
+        // Emit event
+        env.events().publish(
+            (symbol_short!("punish"), swap_id),
+            swap.initiator,
+        );
+    }
+
     fn get_swap(env: Env, swap_id: BytesN<32>) -> SwapOrder {
         let swap_key = DataKey::Swap(swap_id);
         env.storage().persistent().get(&swap_key)
             .expect("Swap does not exist")
     }
+
+    fn list_swaps(env: Env, who: Address, start: u32, limit: u32) -> Vec<SwapOrder> {
+        let index: Vec<BytesN<32>> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::SwapIndex(who))
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let mut swaps = Vec::new(&env);
+        let end = start.saturating_add(limit).min(index.len());
+        for i in start..end {
+            let swap_id = index.get(i).expect("Swap index out of bounds");
+            let swap: SwapOrder = env.storage().persistent().get(&DataKey::Swap(swap_id))
+                .expect("Swap does not exist");
+            swaps.push_back(swap);
+        }
+        swaps
+    }
+
+    fn count_swaps(env: Env, who: Address) -> u32 {
+        env.storage()
+            .persistent()
+            .get::<_, Vec<BytesN<32>>>(&DataKey::SwapIndex(who))
+            .map(|index| index.len())
+            .unwrap_or(0)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,673 @@
+#![cfg(test)]
+
+use super::*;
+use soroban_sdk::testutils::{Address as _, Ledger as _, MockAuth, MockAuthInvoke};
+
+fn setup(env: &Env) -> (StellarHTLCClient<'static>, Address) {
+    let contract_id = env.register_contract(None, StellarHTLC);
+    let client = StellarHTLCClient::new(env, &contract_id);
+    (client, contract_id)
+}
+
+/// Registers a Stellar asset contract to serve as the swap asset and mints
+/// each of `holders` enough balance to fund the swaps a test opens. Minting is
+/// authorized with `mock_all_auths`; a later `set_auths` replaces that mode.
+fn create_token(env: &Env, holders: &[&Address]) -> Address {
+    env.mock_all_auths();
+    let admin = Address::generate(env);
+    let asset = env.register_stellar_asset_contract(admin);
+    let admin_client = soroban_sdk::token::StellarAssetClient::new(env, &asset);
+    for holder in holders {
+        admin_client.mint(holder, &1_000_000);
+    }
+    asset
+}
+
+/// Initiates a swap while authorizing only `initiator` (via explicit
+/// `set_auths`, not `mock_all_auths`), so later calls in the same test can
+/// authorize a different, wrong signer and prove `require_auth()` rejects it.
+#[allow(clippy::too_many_arguments)]
+fn initiate_swap_with_explicit_auth(
+    env: &Env,
+    client: &StellarHTLCClient<'_>,
+    contract_id: &Address,
+    initiator: &Address,
+    participant: &Address,
+    asset: &Address,
+    hashlock: &BytesN<32>,
+    relayer: Option<Address>,
+) -> (BytesN<32>, u32, u32) {
+    let cancel_timelock = env.ledger().sequence() + 1000;
+    let punish_timelock = env.ledger().sequence() + 2000;
+    let ethereum_destination = String::from_str(env, "0xdest");
+    let ethereum_amount = String::from_str(env, "100");
+    let ethereum_token = String::from_str(env, "0xtoken");
+
+    env.set_auths(&[MockAuth {
+        address: initiator,
+        invoke: &MockAuthInvoke {
+            contract: contract_id,
+            fn_name: "initiate_swap",
+            args: (
+                initiator.clone(),
+                participant.clone(),
+                asset.clone(),
+                100i128,
+                hashlock.clone(),
+                HashAlgo::Sha256,
+                cancel_timelock,
+                punish_timelock,
+                relayer.clone(),
+                ethereum_destination.clone(),
+                ethereum_amount.clone(),
+                ethereum_token.clone(),
+            )
+                .into_val(env),
+            sub_invokes: &[MockAuthInvoke {
+                contract: asset,
+                fn_name: "transfer",
+                args: (initiator.clone(), contract_id.clone(), 100i128).into_val(env),
+                sub_invokes: &[],
+            }],
+        },
+    }
+    .into()]);
+
+    let swap_id = client.initiate_swap(
+        initiator,
+        participant,
+        asset,
+        &100,
+        hashlock,
+        &HashAlgo::Sha256,
+        &cancel_timelock,
+        &punish_timelock,
+        &relayer,
+        &ethereum_destination,
+        &ethereum_amount,
+        &ethereum_token,
+    );
+
+    (swap_id, cancel_timelock, punish_timelock)
+}
+
+#[test]
+fn test_concurrent_swaps_with_same_hashlock_do_not_collide_and_settle_independently() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup(&env);
+
+    let initiator = Address::generate(&env);
+    let participant = Address::generate(&env);
+    let asset = create_token(&env, &[&initiator]);
+    let preimage = Bytes::from_array(&env, &[7u8; 32]);
+    let hashlock = env.crypto().sha256(&preimage);
+    let cancel_timelock = env.ledger().sequence() + 1000;
+    let punish_timelock = env.ledger().sequence() + 2000;
+
+    let swap_id_1 = client.initiate_swap(
+        &initiator,
+        &participant,
+        &asset,
+        &100,
+        &hashlock,
+        &HashAlgo::Sha256,
+        &cancel_timelock,
+        &punish_timelock,
+        &None,
+        &String::from_str(&env, "0xdest"),
+        &String::from_str(&env, "100"),
+        &String::from_str(&env, "0xtoken"),
+    );
+
+    let swap_id_2 = client.initiate_swap(
+        &initiator,
+        &participant,
+        &asset,
+        &200,
+        &hashlock,
+        &HashAlgo::Sha256,
+        &cancel_timelock,
+        &punish_timelock,
+        &None,
+        &String::from_str(&env, "0xdest"),
+        &String::from_str(&env, "200"),
+        &String::from_str(&env, "0xtoken"),
+    );
+
+    assert_ne!(swap_id_1, swap_id_2, "swaps sharing a hashlock must get distinct IDs");
+
+    let swap_1 = client.get_swap(&swap_id_1);
+    let swap_2 = client.get_swap(&swap_id_2);
+    assert_eq!(swap_1.amount, 100);
+    assert_eq!(swap_2.amount, 200);
+    assert_eq!(swap_1.hashlock, hashlock);
+    assert_eq!(swap_2.hashlock, hashlock);
+
+    // Settling swap_id_1 must not affect swap_id_2's independent state.
+    client.withdraw(&swap_id_1, &preimage);
+    assert!(client.get_swap(&swap_id_1).withdrawn);
+    let swap_2_after = client.get_swap(&swap_id_2);
+    assert!(!swap_2_after.withdrawn);
+    assert!(!swap_2_after.refunded);
+    assert!(!swap_2_after.cancelled);
+}
+
+#[test]
+fn test_list_swaps_paginates_by_participant() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup(&env);
+
+    let initiator = Address::generate(&env);
+    let participant = Address::generate(&env);
+    let asset = create_token(&env, &[&initiator]);
+    let cancel_timelock = env.ledger().sequence() + 1000;
+    let punish_timelock = env.ledger().sequence() + 2000;
+
+    for i in 0..3u8 {
+        client.initiate_swap(
+            &initiator,
+            &participant,
+            &asset,
+            &(100 + i as i128),
+            &BytesN::from_array(&env, &[i; 32]),
+            &HashAlgo::Sha256,
+            &cancel_timelock,
+            &punish_timelock,
+            &None,
+            &String::from_str(&env, "0xdest"),
+            &String::from_str(&env, "100"),
+            &String::from_str(&env, "0xtoken"),
+        );
+    }
+
+    assert_eq!(client.count_swaps(&participant), 3);
+    assert_eq!(client.count_swaps(&initiator), 3);
+
+    let page = client.list_swaps(&participant, &1, &2);
+    assert_eq!(page.len(), 2);
+    assert_eq!(page.get(0).unwrap().amount, 101);
+    assert_eq!(page.get(1).unwrap().amount, 102);
+}
+
+#[test]
+fn test_swap_index_is_per_party_not_shared_across_all_initiators() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup(&env);
+
+    let initiator_a = Address::generate(&env);
+    let initiator_b = Address::generate(&env);
+    let participant = Address::generate(&env);
+    let asset = create_token(&env, &[&initiator_a, &initiator_b]);
+    let cancel_timelock = env.ledger().sequence() + 1000;
+    let punish_timelock = env.ledger().sequence() + 2000;
+
+    client.initiate_swap(
+        &initiator_a,
+        &participant,
+        &asset,
+        &100,
+        &BytesN::from_array(&env, &[20u8; 32]),
+        &HashAlgo::Sha256,
+        &cancel_timelock,
+        &punish_timelock,
+        &None,
+        &String::from_str(&env, "0xdest"),
+        &String::from_str(&env, "100"),
+        &String::from_str(&env, "0xtoken"),
+    );
+    client.initiate_swap(
+        &initiator_b,
+        &participant,
+        &asset,
+        &200,
+        &BytesN::from_array(&env, &[21u8; 32]),
+        &HashAlgo::Sha256,
+        &cancel_timelock,
+        &punish_timelock,
+        &None,
+        &String::from_str(&env, "0xdest"),
+        &String::from_str(&env, "200"),
+        &String::from_str(&env, "0xtoken"),
+    );
+    client.initiate_swap(
+        &initiator_b,
+        &participant,
+        &asset,
+        &300,
+        &BytesN::from_array(&env, &[22u8; 32]),
+        &HashAlgo::Sha256,
+        &cancel_timelock,
+        &punish_timelock,
+        &None,
+        &String::from_str(&env, "0xdest"),
+        &String::from_str(&env, "300"),
+        &String::from_str(&env, "0xtoken"),
+    );
+
+    // Each initiator's index holds only its own swaps, while the shared
+    // participant's index accumulates across all of them.
+    assert_eq!(client.count_swaps(&initiator_a), 1);
+    assert_eq!(client.count_swaps(&initiator_b), 2);
+    assert_eq!(client.count_swaps(&participant), 3);
+}
+
+#[test]
+fn test_cancel_then_refund_after_cancel_timelock() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup(&env);
+
+    let initiator = Address::generate(&env);
+    let participant = Address::generate(&env);
+    let asset = create_token(&env, &[&initiator]);
+    let hashlock = BytesN::from_array(&env, &[9u8; 32]);
+    let cancel_timelock = env.ledger().sequence() + 1000;
+    let punish_timelock = env.ledger().sequence() + 2000;
+
+    let swap_id = client.initiate_swap(
+        &initiator,
+        &participant,
+        &asset,
+        &100,
+        &hashlock,
+        &HashAlgo::Sha256,
+        &cancel_timelock,
+        &punish_timelock,
+        &None,
+        &String::from_str(&env, "0xdest"),
+        &String::from_str(&env, "100"),
+        &String::from_str(&env, "0xtoken"),
+    );
+
+    env.ledger().set_sequence_number(cancel_timelock);
+    client.cancel(&swap_id, &initiator);
+    assert!(client.get_swap(&swap_id).cancelled);
+
+    client.refund(&swap_id);
+    let swap = client.get_swap(&swap_id);
+    assert!(swap.refunded);
+}
+
+#[test]
+fn test_cancel_by_participant_is_also_allowed() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup(&env);
+
+    let initiator = Address::generate(&env);
+    let participant = Address::generate(&env);
+    let asset = create_token(&env, &[&initiator]);
+    let hashlock = BytesN::from_array(&env, &[10u8; 32]);
+    let cancel_timelock = env.ledger().sequence() + 1000;
+    let punish_timelock = env.ledger().sequence() + 2000;
+
+    let swap_id = client.initiate_swap(
+        &initiator,
+        &participant,
+        &asset,
+        &100,
+        &hashlock,
+        &HashAlgo::Sha256,
+        &cancel_timelock,
+        &punish_timelock,
+        &None,
+        &String::from_str(&env, "0xdest"),
+        &String::from_str(&env, "100"),
+        &String::from_str(&env, "0xtoken"),
+    );
+
+    env.ledger().set_sequence_number(cancel_timelock);
+    client.cancel(&swap_id, &participant);
+    assert!(client.get_swap(&swap_id).cancelled);
+}
+
+#[test]
+fn test_punish_sweeps_funds_to_initiator_after_punish_timelock() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup(&env);
+
+    let initiator = Address::generate(&env);
+    let participant = Address::generate(&env);
+    let asset = create_token(&env, &[&initiator]);
+    let hashlock = BytesN::from_array(&env, &[11u8; 32]);
+    let cancel_timelock = env.ledger().sequence() + 1000;
+    let punish_timelock = env.ledger().sequence() + 2000;
+
+    let swap_id = client.initiate_swap(
+        &initiator,
+        &participant,
+        &asset,
+        &100,
+        &hashlock,
+        &HashAlgo::Sha256,
+        &cancel_timelock,
+        &punish_timelock,
+        &None,
+        &String::from_str(&env, "0xdest"),
+        &String::from_str(&env, "100"),
+        &String::from_str(&env, "0xtoken"),
+    );
+
+    env.ledger().set_sequence_number(punish_timelock);
+    client.punish(&swap_id);
+
+    let swap = client.get_swap(&swap_id);
+    assert!(swap.refunded);
+    assert_eq!(swap.initiator, initiator);
+}
+
+#[test]
+fn test_punish_fails_after_swap_was_cancelled() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup(&env);
+
+    let initiator = Address::generate(&env);
+    let participant = Address::generate(&env);
+    let asset = create_token(&env, &[&initiator]);
+    let hashlock = BytesN::from_array(&env, &[15u8; 32]);
+    let cancel_timelock = env.ledger().sequence() + 1000;
+    let punish_timelock = env.ledger().sequence() + 2000;
+
+    let swap_id = client.initiate_swap(
+        &initiator,
+        &participant,
+        &asset,
+        &100,
+        &hashlock,
+        &HashAlgo::Sha256,
+        &cancel_timelock,
+        &punish_timelock,
+        &None,
+        &String::from_str(&env, "0xdest"),
+        &String::from_str(&env, "100"),
+        &String::from_str(&env, "0xtoken"),
+    );
+
+    env.ledger().set_sequence_number(cancel_timelock);
+    client.cancel(&swap_id, &initiator);
+
+    env.ledger().set_sequence_number(punish_timelock);
+    let result = client.try_punish(&swap_id);
+    assert!(result.is_err(), "punish must not fire once the swap has been cancelled");
+}
+
+#[test]
+fn test_withdraw_for_relayer_forwards_to_designated_recipient() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup(&env);
+
+    let initiator = Address::generate(&env);
+    let participant = Address::generate(&env);
+    let asset = create_token(&env, &[&initiator]);
+    let relayer = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    let preimage = Bytes::from_array(&env, &[3u8; 32]);
+    let hashlock = env.crypto().sha256(&preimage);
+    let cancel_timelock = env.ledger().sequence() + 1000;
+    let punish_timelock = env.ledger().sequence() + 2000;
+
+    let swap_id = client.initiate_swap(
+        &initiator,
+        &participant,
+        &asset,
+        &100,
+        &hashlock,
+        &HashAlgo::Sha256,
+        &cancel_timelock,
+        &punish_timelock,
+        &Some(relayer),
+        &String::from_str(&env, "0xdest"),
+        &String::from_str(&env, "100"),
+        &String::from_str(&env, "0xtoken"),
+    );
+
+    client.withdraw_for(&swap_id, &preimage, &recipient);
+
+    let swap = client.get_swap(&swap_id);
+    assert!(swap.withdrawn);
+}
+
+#[test]
+#[should_panic(expected = "Swap has no authorized relayer")]
+fn test_withdraw_for_fails_without_relayer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup(&env);
+
+    let initiator = Address::generate(&env);
+    let participant = Address::generate(&env);
+    let asset = create_token(&env, &[&initiator]);
+    let recipient = Address::generate(&env);
+    let preimage = Bytes::from_array(&env, &[4u8; 32]);
+    let hashlock = env.crypto().sha256(&preimage);
+    let cancel_timelock = env.ledger().sequence() + 1000;
+    let punish_timelock = env.ledger().sequence() + 2000;
+
+    let swap_id = client.initiate_swap(
+        &initiator,
+        &participant,
+        &asset,
+        &100,
+        &hashlock,
+        &HashAlgo::Sha256,
+        &cancel_timelock,
+        &punish_timelock,
+        &None,
+        &String::from_str(&env, "0xdest"),
+        &String::from_str(&env, "100"),
+        &String::from_str(&env, "0xtoken"),
+    );
+
+    client.withdraw_for(&swap_id, &preimage, &recipient);
+}
+
+#[test]
+fn test_keccak256_swap_rejects_sha256_preimage_and_vice_versa() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup(&env);
+
+    let initiator = Address::generate(&env);
+    let participant = Address::generate(&env);
+    let asset = create_token(&env, &[&initiator]);
+    let preimage = Bytes::from_array(&env, &[5u8; 32]);
+    let sha_hashlock = env.crypto().sha256(&preimage);
+    let keccak_hashlock = env.crypto().keccak256(&preimage);
+    assert_ne!(sha_hashlock, keccak_hashlock, "fixture preimage must hash differently under each algorithm");
+    let cancel_timelock = env.ledger().sequence() + 1000;
+    let punish_timelock = env.ledger().sequence() + 2000;
+
+    // Locked with the correct sha256 digest but verified as a keccak256 swap.
+    let mismatched_keccak_swap_id = client.initiate_swap(
+        &initiator,
+        &participant,
+        &asset,
+        &100,
+        &sha_hashlock,
+        &HashAlgo::Keccak256,
+        &cancel_timelock,
+        &punish_timelock,
+        &None,
+        &String::from_str(&env, "0xdest"),
+        &String::from_str(&env, "100"),
+        &String::from_str(&env, "0xtoken"),
+    );
+    let result = client.try_withdraw(&mismatched_keccak_swap_id, &preimage);
+    assert!(result.is_err(), "keccak256 swap must reject a sha256-derived hashlock");
+
+    // Locked with the correct keccak256 digest but verified as a sha256 swap.
+    let mismatched_sha_swap_id = client.initiate_swap(
+        &initiator,
+        &participant,
+        &asset,
+        &100,
+        &keccak_hashlock,
+        &HashAlgo::Sha256,
+        &cancel_timelock,
+        &punish_timelock,
+        &None,
+        &String::from_str(&env, "0xdest"),
+        &String::from_str(&env, "100"),
+        &String::from_str(&env, "0xtoken"),
+    );
+    let result = client.try_withdraw(&mismatched_sha_swap_id, &preimage);
+    assert!(result.is_err(), "sha256 swap must reject a keccak256-derived hashlock");
+
+    // The correctly-paired algorithm and hashlock still unlocks normally.
+    let matching_swap_id = client.initiate_swap(
+        &initiator,
+        &participant,
+        &asset,
+        &100,
+        &keccak_hashlock,
+        &HashAlgo::Keccak256,
+        &cancel_timelock,
+        &punish_timelock,
+        &None,
+        &String::from_str(&env, "0xdest"),
+        &String::from_str(&env, "100"),
+        &String::from_str(&env, "0xtoken"),
+    );
+    client.withdraw(&matching_swap_id, &preimage);
+    assert!(client.get_swap(&matching_swap_id).withdrawn);
+}
+
+#[test]
+fn test_withdraw_rejects_a_signer_who_is_not_the_participant() {
+    let env = Env::default();
+    let (client, contract_id) = setup(&env);
+
+    let initiator = Address::generate(&env);
+    let participant = Address::generate(&env);
+    let impostor = Address::generate(&env);
+    let asset = create_token(&env, &[&initiator]);
+    let preimage = Bytes::from_array(&env, &[6u8; 32]);
+    let hashlock = env.crypto().sha256(&preimage);
+
+    let (swap_id, _, _) = initiate_swap_with_explicit_auth(
+        &env, &client, &contract_id, &initiator, &participant, &asset, &hashlock, None,
+    );
+
+    env.set_auths(&[MockAuth {
+        address: &impostor,
+        invoke: &MockAuthInvoke {
+            contract: &contract_id,
+            fn_name: "withdraw",
+            args: (swap_id.clone(), preimage.clone()).into_val(&env),
+            sub_invokes: &[],
+        },
+    }
+    .into()]);
+    let result = client.try_withdraw(&swap_id, &preimage);
+    assert!(result.is_err(), "withdraw must reject a signer who is not the participant");
+}
+
+#[test]
+fn test_withdraw_for_rejects_a_signer_who_is_not_the_relayer() {
+    let env = Env::default();
+    let (client, contract_id) = setup(&env);
+
+    let initiator = Address::generate(&env);
+    let participant = Address::generate(&env);
+    let relayer = Address::generate(&env);
+    let impostor = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    let asset = create_token(&env, &[&initiator]);
+    let preimage = Bytes::from_array(&env, &[12u8; 32]);
+    let hashlock = env.crypto().sha256(&preimage);
+
+    let (swap_id, _, _) = initiate_swap_with_explicit_auth(
+        &env, &client, &contract_id, &initiator, &participant, &asset, &hashlock, Some(relayer),
+    );
+
+    env.set_auths(&[MockAuth {
+        address: &impostor,
+        invoke: &MockAuthInvoke {
+            contract: &contract_id,
+            fn_name: "withdraw_for",
+            args: (swap_id.clone(), preimage.clone(), recipient.clone()).into_val(&env),
+            sub_invokes: &[],
+        },
+    }
+    .into()]);
+    let result = client.try_withdraw_for(&swap_id, &preimage, &recipient);
+    assert!(result.is_err(), "withdraw_for must reject a signer who is not the designated relayer");
+}
+
+#[test]
+fn test_refund_rejects_a_signer_who_is_not_the_initiator() {
+    let env = Env::default();
+    let (client, contract_id) = setup(&env);
+
+    let initiator = Address::generate(&env);
+    let participant = Address::generate(&env);
+    let impostor = Address::generate(&env);
+    let asset = create_token(&env, &[&initiator]);
+    let hashlock = BytesN::from_array(&env, &[13u8; 32]);
+
+    let (swap_id, cancel_timelock, _) = initiate_swap_with_explicit_auth(
+        &env, &client, &contract_id, &initiator, &participant, &asset, &hashlock, None,
+    );
+
+    env.ledger().set_sequence_number(cancel_timelock);
+    env.set_auths(&[MockAuth {
+        address: &initiator,
+        invoke: &MockAuthInvoke {
+            contract: &contract_id,
+            fn_name: "cancel",
+            args: (swap_id.clone(), initiator.clone()).into_val(&env),
+            sub_invokes: &[],
+        },
+    }
+    .into()]);
+    client.cancel(&swap_id, &initiator);
+
+    env.set_auths(&[MockAuth {
+        address: &impostor,
+        invoke: &MockAuthInvoke {
+            contract: &contract_id,
+            fn_name: "refund",
+            args: (swap_id.clone(),).into_val(&env),
+            sub_invokes: &[],
+        },
+    }
+    .into()]);
+    let result = client.try_refund(&swap_id);
+    assert!(result.is_err(), "refund must reject a signer who is not the initiator");
+}
+
+#[test]
+fn test_punish_rejects_a_signer_who_is_not_the_initiator() {
+    let env = Env::default();
+    let (client, contract_id) = setup(&env);
+
+    let initiator = Address::generate(&env);
+    let participant = Address::generate(&env);
+    let impostor = Address::generate(&env);
+    let asset = create_token(&env, &[&initiator]);
+    let hashlock = BytesN::from_array(&env, &[14u8; 32]);
+
+    let (swap_id, _, punish_timelock) = initiate_swap_with_explicit_auth(
+        &env, &client, &contract_id, &initiator, &participant, &asset, &hashlock, None,
+    );
+
+    env.ledger().set_sequence_number(punish_timelock);
+    env.set_auths(&[MockAuth {
+        address: &impostor,
+        invoke: &MockAuthInvoke {
+            contract: &contract_id,
+            fn_name: "punish",
+            args: (swap_id.clone(),).into_val(&env),
+            sub_invokes: &[],
+        },
+    }
+    .into()]);
+    let result = client.try_punish(&swap_id);
+    assert!(result.is_err(), "punish must reject a signer who is not the initiator");
+}